@@ -1,7 +1,4 @@
-use actyxos_sdk::{
-    event::{Event, OffsetMap},
-    Payload,
-};
+use actyxos_sdk::event::{Event, OffsetMap, Payload};
 use anyhow::Result;
 use async_trait::async_trait;
 
@@ -1,19 +1,27 @@
-use crate::postgres::Postgres;
+use crate::postgres::{Postgres, SslMode};
+use crate::schema_map::SchemaMap;
+use crate::sqlx_db::SqlxDb;
 use actyxos_sdk::event_service::{EventService, Subscription};
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use backtrace::Backtrace;
 use db::{Db, DbConnection};
 use env_logger::Env;
 use futures::{future::FutureExt, stream::StreamExt};
 use futures_batch::ChunksTimeoutStreamExt;
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 use structopt::StructOpt;
 use tokio_compat_02::FutureExt as FutureExt02;
 use tracing::*;
 
 mod db;
+mod metrics;
 mod postgres;
+mod schema_map;
+mod sqlx_db;
 
 #[derive(StructOpt, Debug, Serialize, Deserialize)]
 #[structopt(
@@ -26,18 +34,94 @@ struct Opt {
     max_batch_records: usize,
     #[structopt(long, short = "s", env, default_value = "1")]
     max_batch_seconds: u64,
-    #[structopt(long, short = "d", env)]
-    db_name: String,
+    #[structopt(
+        about = "Required unless --db-url is set",
+        long,
+        short = "d",
+        env
+    )]
+    db_name: Option<String>,
     #[structopt(long, short = "h", env, default_value = "localhost")]
     db_host: String,
     #[structopt(long, short = "p", env, default_value = "5432")]
     db_port: u16,
-    #[structopt(long, short = "u", env)]
-    db_user: String,
-    #[structopt(long, short = "w", env, hide_env_values = true)]
-    db_password: String,
+    #[structopt(
+        about = "Required unless --db-url is set",
+        long,
+        short = "u",
+        env
+    )]
+    db_user: Option<String>,
+    #[structopt(
+        about = "Required unless --db-url is set",
+        long,
+        short = "w",
+        env,
+        hide_env_values = true
+    )]
+    db_password: Option<String>,
     #[structopt(long, short = "t", env, default_value = "events")]
     table: String,
+    #[structopt(
+        about = "A sqlx connection URL (postgres://, mysql://, or sqlite://). If set, bypasses \
+                 --db-host/--db-port/... and the Postgres-specific backend (TLS, pooling, \
+                 retry/dead-lettering, schema-map, notify) in favour of a portable sqlx sink",
+        long,
+        env
+    )]
+    db_url: Option<String>,
+    #[structopt(
+        about = "TLS mode for the database connection: disable, require, or verify-full",
+        long,
+        env,
+        default_value = "disable"
+    )]
+    sslmode: SslMode,
+    #[structopt(
+        about = "Path to a PEM-encoded root CA certificate used to validate the server under verify-full (require accepts any server certificate)",
+        long,
+        env
+    )]
+    ssl_root_cert: Option<String>,
+    #[structopt(
+        about = "Maximum number of pooled connections held open to the database",
+        long,
+        env,
+        default_value = "10"
+    )]
+    db_pool_size: usize,
+    #[structopt(
+        about = "Maximum number of attempts for a retryable batch insert before falling back to per-row dead lettering",
+        long,
+        env,
+        default_value = "5"
+    )]
+    max_insert_retries: u32,
+    #[structopt(
+        about = "Path to a JSON file mapping payload JSON pointers (e.g. $.price) to typed columns",
+        long,
+        env
+    )]
+    schema_map: Option<PathBuf>,
+    #[structopt(
+        about = "If set, issue pg_notify on this channel after each committed batch",
+        long,
+        env
+    )]
+    notify_channel: Option<String>,
+    #[structopt(
+        about = "If set, expose a Prometheus /metrics endpoint on this address",
+        long,
+        env
+    )]
+    metrics_addr: Option<SocketAddr>,
+    #[structopt(
+        about = "How often, in seconds, to re-poll the store and database offsets to publish lag metrics",
+        long,
+        env,
+        default_value = "10"
+    )]
+    metrics_lag_poll_seconds: u64,
     #[structopt(
         about = "Subscriptions to subscribe to",
         env,
@@ -59,37 +143,69 @@ pub async fn main() -> Result<()> {
 
     info!("Subscribing to: {:?}", opt.subscriptions);
 
-    let pg = Postgres::new(
-        opt.db_host,
-        opt.db_port,
-        opt.db_user,
-        opt.db_password,
-        opt.db_name,
-        opt.table,
-    );
+    if let Some(db_url) = opt.db_url {
+        let sink = SqlxDb::new(db_url, opt.table);
+        run_pipeline(
+            Box::new(sink),
+            opt.subscriptions,
+            opt.max_batch_records,
+            opt.max_batch_seconds,
+            opt.metrics_addr,
+            opt.metrics_lag_poll_seconds,
+        )
+        .compat()
+        .await
+    } else {
+        let schema_map = match opt.schema_map {
+            Some(path) => SchemaMap::load(&path)?,
+            None => SchemaMap::default(),
+        };
 
-    run_pipeline(
-        Box::new(pg),
-        opt.subscriptions,
-        opt.max_batch_records,
-        opt.max_batch_seconds,
-    )
-    .compat()
-    .await
+        let pg = Postgres::new(
+            opt.db_host,
+            opt.db_port,
+            opt.db_user
+                .ok_or_else(|| anyhow!("--db-user is required unless --db-url is set"))?,
+            opt.db_password
+                .ok_or_else(|| anyhow!("--db-password is required unless --db-url is set"))?,
+            opt.db_name
+                .ok_or_else(|| anyhow!("--db-name is required unless --db-url is set"))?,
+            opt.table,
+            opt.sslmode,
+            opt.ssl_root_cert,
+            opt.db_pool_size,
+            opt.max_insert_retries,
+            schema_map,
+            opt.notify_channel,
+        );
+
+        run_pipeline(
+            Box::new(pg),
+            opt.subscriptions,
+            opt.max_batch_records,
+            opt.max_batch_seconds,
+            opt.metrics_addr,
+            opt.metrics_lag_poll_seconds,
+        )
+        .compat()
+        .await
+    }
 }
 
-async fn run_pipeline<C: DbConnection + 'static>(
+async fn run_pipeline<C: DbConnection + Send + Sync + 'static>(
     db: Box<dyn Db<C>>,
     subscriptions: Vec<Subscription>,
     max_batch_records: usize,
     max_batch_seconds: u64,
+    metrics_addr: Option<SocketAddr>,
+    metrics_lag_poll_seconds: u64,
 ) -> Result<()> {
     let event_service = EventService::default();
     debug!("Connected to EventService");
 
     let store_offsets = event_service.get_offsets().await?;
 
-    let db = db.connect().await?;
+    let db = Arc::new(db.connect().await?);
     let db_offsets = db.get_offsets().await?;
     info!("Offset map from database: {:?}", db_offsets);
     info!("Offset map from store:    {:?}", store_offsets);
@@ -100,6 +216,12 @@ async fn run_pipeline<C: DbConnection + 'static>(
         &store_offsets.size()
     );
 
+    if let Some(addr) = metrics_addr {
+        metrics::serve(addr).await?;
+        let db = Arc::clone(&db);
+        tokio::spawn(poll_offset_lag(db, metrics_lag_poll_seconds));
+    }
+
     event_service
         .subscribe_from(db_offsets, subscriptions)
         .await?
@@ -110,6 +232,37 @@ async fn run_pipeline<C: DbConnection + 'static>(
     Ok(())
 }
 
+/// Periodically re-polls the store and database offset maps and publishes
+/// their per-source difference as the `events_to_db_offset_lag` gauge.
+async fn poll_offset_lag<C: DbConnection + Send + Sync + 'static>(
+    db: Arc<C>,
+    interval_seconds: u64,
+) {
+    let event_service = EventService::default();
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_seconds));
+
+    loop {
+        interval.tick().await;
+
+        let store_offsets = match event_service.get_offsets().await {
+            Ok(offsets) => offsets,
+            Err(err) => {
+                warn!("Failed to poll store offsets for lag metrics: {:?}", err);
+                continue;
+            }
+        };
+        let db_offsets = match db.get_offsets().await {
+            Ok(offsets) => offsets,
+            Err(err) => {
+                warn!("Failed to poll db offsets for lag metrics: {:?}", err);
+                continue;
+            }
+        };
+
+        metrics::update_offset_lag(&store_offsets, &db_offsets);
+    }
+}
+
 /// sets up a panic hook that dumps all available info and exits the process with a non-zero exit code.
 ///
 /// the panic hook is a global, but calling this method multiple times is fine
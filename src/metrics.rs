@@ -0,0 +1,122 @@
+use actyxos_sdk::event::{Offset, OffsetMap};
+use anyhow::Result;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use once_cell::sync::Lazy;
+use prometheus::{
+    Encoder, GaugeVec, Histogram, HistogramOpts, IntCounter, Opts, Registry, TextEncoder,
+};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use tracing::{error, info};
+
+static REGISTRY: Lazy<Registry> = Lazy::new(Registry::new);
+
+/// Total number of events successfully inserted into the main table.
+pub static EVENTS_INSERTED_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
+    let c = IntCounter::new(
+        "events_to_db_events_inserted_total",
+        "Total number of events inserted into the database",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(c.clone())).unwrap();
+    c
+});
+
+/// Latency of a single batch insert (including retries), in seconds.
+pub static INSERT_LATENCY_SECONDS: Lazy<Histogram> = Lazy::new(|| {
+    let h = Histogram::with_opts(HistogramOpts::new(
+        "events_to_db_insert_latency_seconds",
+        "Latency of a batch insert, in seconds",
+    ))
+    .unwrap();
+    REGISTRY.register(Box::new(h.clone())).unwrap();
+    h
+});
+
+/// Number of events carried by each batch passed to `DbConnection::insert`.
+pub static BATCH_SIZE: Lazy<Histogram> = Lazy::new(|| {
+    let h = Histogram::with_opts(HistogramOpts::new(
+        "events_to_db_batch_size",
+        "Number of events per inserted batch",
+    ))
+    .unwrap();
+    REGISTRY.register(Box::new(h.clone())).unwrap();
+    h
+});
+
+/// Number of batch-insert retries performed due to retryable errors.
+pub static INSERT_RETRIES_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
+    let c = IntCounter::new(
+        "events_to_db_insert_retries_total",
+        "Total number of batch insert retries",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(c.clone())).unwrap();
+    c
+});
+
+/// Number of rows routed to the dead letter table instead of the main table.
+pub static DEAD_LETTERED_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
+    let c = IntCounter::new(
+        "events_to_db_dead_lettered_total",
+        "Total number of rows routed to the dead letter table",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(c.clone())).unwrap();
+    c
+});
+
+/// `store max psn - db max psn`, per source. Positive means the database is
+/// behind the event service.
+pub static OFFSET_LAG: Lazy<GaugeVec> = Lazy::new(|| {
+    let g = GaugeVec::new(
+        Opts::new(
+            "events_to_db_offset_lag",
+            "How many events behind the store this source's data in the database is",
+        ),
+        &["source"],
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(g.clone())).unwrap();
+    g
+});
+
+/// Starts the `/metrics` HTTP endpoint in the background.
+pub async fn serve(addr: SocketAddr) -> Result<()> {
+    let make_svc =
+        make_service_fn(|_conn| async { Ok::<_, Infallible>(service_fn(handle_request)) });
+    let server = Server::try_bind(&addr)?.serve(make_svc);
+    info!("Metrics endpoint listening on http://{}/metrics", addr);
+
+    tokio::spawn(async move {
+        if let Err(err) = server.await {
+            error!("Metrics server error: {:?}", err);
+        }
+    });
+
+    Ok(())
+}
+
+/// Publishes `store - db` as the current lag for every source present in `store`.
+pub fn update_offset_lag(store: &OffsetMap, db: &OffsetMap) {
+    for (source, store_offset) in store.iter() {
+        let store_psn = (store_offset - Offset::ZERO) as i64;
+        let db_psn = db
+            .iter()
+            .find(|(s, _)| *s == source)
+            .map(|(_, o)| (o - Offset::ZERO) as i64)
+            .unwrap_or(-1);
+        OFFSET_LAG
+            .with_label_values(&[source.as_str()])
+            .set((store_psn - db_psn) as f64);
+    }
+}
+
+async fn handle_request(_req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let encoder = TextEncoder::new();
+    let metric_families = REGISTRY.gather();
+    let mut buffer = Vec::new();
+    encoder.encode(&metric_families, &mut buffer).unwrap();
+    Ok(Response::new(Body::from(buffer)))
+}
@@ -1,16 +1,180 @@
 use crate::db::{Db, DbConnection};
-use actyxos_sdk::{
-    event::{Event, SourceId},
-    Offset, OffsetMap, Payload,
-};
-use anyhow::Result;
+use crate::metrics;
+use crate::schema_map::{column_ddl, SchemaMap};
+use actyxos_sdk::event::{Event, Offset, OffsetMap, Payload, SourceId};
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use backoff::{backoff::Backoff, ExponentialBackoff};
+use chrono::{DateTime, Utc};
+use deadpool::managed::{Manager, Pool, RecycleError, RecycleResult};
+use rustls::{Certificate, ClientConfig, RootCertStore};
 use serde_cbor::error::Error;
 use serde_json::Value;
-use std::{collections::BTreeMap, convert::TryFrom, time::Instant};
-use tokio_postgres::{types::Type, NoTls};
+use std::{
+    collections::BTreeMap, convert::TryFrom, fmt, fs, str::FromStr, sync::Arc, time::Duration,
+    time::Instant,
+};
+use tokio_postgres::types::ToSql;
+use tokio_postgres::{error::SqlState, types::Type, NoTls};
 use tokio_postgres::{Client, Statement};
-use tracing::{debug, error, info};
+use tokio_postgres_rustls::MakeRustlsConnect;
+use tracing::{debug, error, info, warn};
+
+/// How a connection to Postgres should be secured.
+///
+/// Mirrors libpq's `sslmode`, restricted to the subset we actually support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SslMode {
+    /// Plaintext connection, no TLS.
+    Disable,
+    /// TLS, but the server certificate is not validated.
+    Require,
+    /// TLS with full server certificate and hostname verification.
+    VerifyFull,
+}
+
+impl FromStr for SslMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "disable" => Ok(SslMode::Disable),
+            "require" => Ok(SslMode::Require),
+            "verify-full" => Ok(SslMode::VerifyFull),
+            other => Err(anyhow!(
+                "invalid sslmode '{}': expected one of disable, require, verify-full",
+                other
+            )),
+        }
+    }
+}
+
+/// A `rustls::ServerCertVerifier` that accepts any certificate chain.
+///
+/// Used for `SslMode::Require`, where we want an encrypted channel but are
+/// not asked to validate who is on the other end of it.
+struct NoCertificateVerification;
+
+impl rustls::ServerCertVerifier for NoCertificateVerification {
+    fn verify_server_cert(
+        &self,
+        _roots: &RootCertStore,
+        _presented_certs: &[Certificate],
+        _dns_name: webpki::DNSNameRef<'_>,
+        _ocsp_response: &[u8],
+    ) -> Result<rustls::ServerCertVerified, rustls::TLSError> {
+        Ok(rustls::ServerCertVerified::assertion())
+    }
+}
+
+fn make_rustls_connect(mode: SslMode, root_cert: Option<&str>) -> Result<MakeRustlsConnect> {
+    let mut config = ClientConfig::new();
+
+    if let Some(path) = root_cert {
+        let pem = fs::read(path)
+            .map_err(|e| anyhow!("failed to read --ssl-root-cert {}: {}", path, e))?;
+        let mut store = RootCertStore::empty();
+        store
+            .add_pem_file(&mut &pem[..])
+            .map_err(|_| anyhow!("failed to parse root CA certificate at {}", path))?;
+        config.root_store = store;
+    } else {
+        config
+            .root_store
+            .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+    }
+
+    if mode == SslMode::Require {
+        config
+            .dangerous()
+            .set_certificate_verifier(Arc::new(NoCertificateVerification));
+    }
+
+    Ok(MakeRustlsConnect::new(config))
+}
+
+/// An event that could not be turned into a row for the main table, kept
+/// around so it can be routed into `<table>_dead_letter` instead of dropped.
+#[derive(Debug, PartialEq)]
+pub struct DeadLetterRow<'a> {
+    pub source: &'a str,
+    pub psn: i64,
+    pub lamport: i64,
+    pub raw: Vec<u8>,
+    pub error: String,
+}
+
+/// A single schema-mapped column, accumulated in lockstep with the main
+/// `DbEventVec` rows (one entry per successfully extracted event, `None`
+/// where the payload didn't have the pointed-to field).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProjectedColumn {
+    Float8(Vec<Option<f64>>),
+    Int8(Vec<Option<i64>>),
+    Text(Vec<Option<String>>),
+    Bool(Vec<Option<bool>>),
+    TimestampTz(Vec<Option<DateTime<Utc>>>),
+}
+
+impl ProjectedColumn {
+    fn empty(sql_type: &str) -> Result<ProjectedColumn> {
+        match column_ddl(sql_type)? {
+            "float8" => Ok(ProjectedColumn::Float8(Vec::new())),
+            "int8" => Ok(ProjectedColumn::Int8(Vec::new())),
+            "text" => Ok(ProjectedColumn::Text(Vec::new())),
+            "bool" => Ok(ProjectedColumn::Bool(Vec::new())),
+            "timestamptz" => Ok(ProjectedColumn::TimestampTz(Vec::new())),
+            other => unreachable!("column_ddl returned an unhandled type '{}'", other),
+        }
+    }
+
+    fn push_json(&mut self, value: Option<&Value>) {
+        match self {
+            ProjectedColumn::Float8(v) => v.push(value.and_then(Value::as_f64)),
+            ProjectedColumn::Int8(v) => v.push(value.and_then(Value::as_i64)),
+            ProjectedColumn::Text(v) => v.push(value.and_then(Value::as_str).map(str::to_owned)),
+            ProjectedColumn::Bool(v) => v.push(value.and_then(Value::as_bool)),
+            ProjectedColumn::TimestampTz(v) => v.push(
+                value
+                    .and_then(Value::as_str)
+                    .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                    .map(|dt| dt.with_timezone(&Utc)),
+            ),
+        }
+    }
+
+    fn pg_array_type(&self) -> Type {
+        match self {
+            ProjectedColumn::Float8(_) => Type::FLOAT8_ARRAY,
+            ProjectedColumn::Int8(_) => Type::INT8_ARRAY,
+            ProjectedColumn::Text(_) => Type::TEXT_ARRAY,
+            ProjectedColumn::Bool(_) => Type::BOOL_ARRAY,
+            ProjectedColumn::TimestampTz(_) => Type::TIMESTAMPTZ_ARRAY,
+        }
+    }
+
+    fn as_sql_param(&self) -> &(dyn ToSql + Sync) {
+        match self {
+            ProjectedColumn::Float8(v) => v,
+            ProjectedColumn::Int8(v) => v,
+            ProjectedColumn::Text(v) => v,
+            ProjectedColumn::Bool(v) => v,
+            ProjectedColumn::TimestampTz(v) => v,
+        }
+    }
+
+    /// Extracts a single-element column holding just row `i`, for the
+    /// per-row insert fallback.
+    fn single_row(&self, i: usize) -> ProjectedColumn {
+        match self {
+            ProjectedColumn::Float8(v) => ProjectedColumn::Float8(vec![v[i]]),
+            ProjectedColumn::Int8(v) => ProjectedColumn::Int8(vec![v[i]]),
+            ProjectedColumn::Text(v) => ProjectedColumn::Text(vec![v[i].clone()]),
+            ProjectedColumn::Bool(v) => ProjectedColumn::Bool(vec![v[i]]),
+            ProjectedColumn::TimestampTz(v) => ProjectedColumn::TimestampTz(vec![v[i]]),
+        }
+    }
+}
 
 #[derive(Debug, PartialEq)]
 pub struct DbEventVec<'a> {
@@ -21,6 +185,9 @@ pub struct DbEventVec<'a> {
     pub offsets: Vec<i64>,
     pub timestamps: Vec<i64>,
     pub payloads: Vec<Value>,
+    pub dead_letters: Vec<DeadLetterRow<'a>>,
+    /// Projected columns, keyed by the SQL column name, in schema-map order.
+    pub projected: Vec<(String, ProjectedColumn)>,
 }
 
 impl<'a> DbEventVec<'a> {
@@ -33,13 +200,21 @@ impl<'a> DbEventVec<'a> {
             offsets: Vec::with_capacity(capacity),
             timestamps: Vec::with_capacity(capacity),
             payloads: Vec::with_capacity(capacity),
+            dead_letters: Vec::new(),
+            projected: Vec::new(),
         }
     }
-}
 
-impl<'a> From<&'a [Event<Payload>]> for DbEventVec<'a> {
-    fn from(events: &'a [Event<Payload>]) -> DbEventVec<'a> {
+    /// Builds a `DbEventVec` from a batch of events, additionally projecting
+    /// out the fields declared in `schema_map` into their own parallel
+    /// column vectors.
+    pub fn build(events: &'a [Event<Payload>], schema_map: &SchemaMap) -> Result<DbEventVec<'a>> {
         let mut rows = DbEventVec::empty(events.len());
+        for (_, col) in schema_map.projections() {
+            rows.projected
+                .push((col.column.clone(), ProjectedColumn::empty(&col.sql_type)?));
+        }
+
         for e in events {
             let ev: Result<Event<Value>, Error> = e.extract();
             match ev {
@@ -50,6 +225,15 @@ impl<'a> From<&'a [Event<Payload>]> for DbEventVec<'a> {
                     rows.lamports.push(e.lamport.as_i64());
                     rows.offsets.push((e.offset - Offset::ZERO) as i64);
                     rows.timestamps.push(e.timestamp.as_i64());
+                    for (pointer, col) in schema_map.projections() {
+                        let value = SchemaMap::extract(pointer, &ev.payload);
+                        let (_, projected) = rows
+                            .projected
+                            .iter_mut()
+                            .find(|(name, _)| name == &col.column)
+                            .expect("projected column was seeded from the same schema_map above");
+                        projected.push_json(value);
+                    }
                     rows.payloads.push(ev.payload);
                 }
                 Err(err) => {
@@ -57,17 +241,167 @@ impl<'a> From<&'a [Event<Payload>]> for DbEventVec<'a> {
                         "Error parsing payload as JSON: {:?}.\n     Event: {:?}",
                         err, e
                     );
+                    rows.dead_letters.push(DeadLetterRow {
+                        source: e.stream.source.as_str(),
+                        psn: (e.offset - Offset::ZERO) as i64,
+                        lamport: e.lamport.as_i64(),
+                        raw: e.payload.json_string().into_bytes(),
+                        error: err.to_string(),
+                    });
                 }
             }
         }
-        rows
+        Ok(rows)
+    }
+}
+
+/// Opens a single `tokio_postgres::Client`, honouring `sslmode`, and spawns
+/// its backing connection future so the client can actually drive queries.
+async fn connect_client(
+    conn_str: &str,
+    sslmode: SslMode,
+    ssl_root_cert: Option<&str>,
+) -> Result<Client> {
+    let client = if sslmode == SslMode::Disable {
+        let (client, connection) = tokio_postgres::connect(conn_str, NoTls).await?;
+        tokio::spawn(Box::pin(connection));
+        client
+    } else {
+        let connector = make_rustls_connect(sslmode, ssl_root_cert)?;
+        let (client, connection) = tokio_postgres::connect(conn_str, connector).await?;
+        tokio::spawn(Box::pin(connection));
+        client
+    };
+    Ok(client)
+}
+
+/// Prepares the batch `INSERT ... unnest(...)` statement against `client`,
+/// appending one `unnest` argument per schema-mapped column after the fixed
+/// metadata/payload columns.
+///
+/// Prepared statements are per-connection, so this has to be run again every
+/// time we pick up a (possibly new) connection from the pool.
+async fn prepare_insert_stmt(
+    client: &Client,
+    table: &str,
+    projected: &[(String, ProjectedColumn)],
+) -> Result<Statement> {
+    let mut columns = vec![
+        "source", "semantics", "name", "seq", "psn", "timestamp", "payload",
+    ];
+    let mut types = vec![
+        Type::TEXT_ARRAY,
+        Type::TEXT_ARRAY,
+        Type::TEXT_ARRAY,
+        Type::INT8_ARRAY,
+        Type::INT8_ARRAY,
+        Type::INT8_ARRAY,
+        Type::JSONB_ARRAY,
+    ];
+    for (name, col) in projected {
+        columns.push(name.as_str());
+        types.push(col.pg_array_type());
+    }
+
+    let placeholders = (1..=columns.len())
+        .map(|i| format!("${}", i))
+        .collect::<Vec<_>>()
+        .join(",");
+    let sql = format!(
+        r#"INSERT INTO {} ({})
+             SELECT * FROM unnest({})
+             ON CONFLICT DO NOTHING"#,
+        table,
+        columns.join(", "),
+        placeholders
+    );
+    Ok(client.prepare_typed(&sql, &types).await?)
+}
+
+/// Prepares the single-row dead-letter insert statement against `client`.
+async fn prepare_dead_letter_stmt(client: &Client, table: &str) -> Result<Statement> {
+    let sql = format!(
+        r#"INSERT INTO {}_dead_letter (source, psn, lamport, raw, error)
+             VALUES ($1, $2, $3, $4, $5)"#,
+        table
+    );
+    Ok(client.prepare(&sql).await?)
+}
+
+/// Whether a failed query is worth retrying (connection trouble, transient
+/// serialization conflicts) as opposed to a permanent rejection of the row
+/// data itself (e.g. a constraint violation).
+fn is_retryable(err: &tokio_postgres::Error) -> bool {
+    match err.code() {
+        Some(code) => matches!(
+            *code,
+            SqlState::CONNECTION_EXCEPTION
+                | SqlState::CONNECTION_DOES_NOT_EXIST
+                | SqlState::CONNECTION_FAILURE
+                | SqlState::SQLCLIENT_UNABLE_TO_ESTABLISH_SQLCONNECTION
+                | SqlState::SQLSERVER_REJECTED_ESTABLISHMENT_OF_SQLCONNECTION
+                | SqlState::T_R_SERIALIZATION_FAILURE
+                | SqlState::T_R_DEADLOCK_DETECTED
+        ),
+        // No SqlState at all means the error never made it to the server
+        // (io error, connection closed) - worth a retry.
+        None => true,
     }
 }
 
+/// Error surfaced by [`PgManager`] to `deadpool` when it can't (re)establish
+/// a pooled connection.
+#[derive(Debug)]
+pub struct PoolConnectError(anyhow::Error);
+
+impl fmt::Display for PoolConnectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for PoolConnectError {}
+
+/// `deadpool` connection manager that opens a fresh `tokio_postgres::Client`
+/// per pool slot and reconnects whenever the backing TCP connection is lost
+/// (DB restart, idle timeout, network blip).
+struct PgManager {
+    conn_str: String,
+    sslmode: SslMode,
+    ssl_root_cert: Option<String>,
+}
+
+#[async_trait]
+impl Manager for PgManager {
+    type Type = Client;
+    type Error = PoolConnectError;
+
+    async fn create(&self) -> Result<Client, Self::Error> {
+        connect_client(&self.conn_str, self.sslmode, self.ssl_root_cert.as_deref())
+            .await
+            .map_err(PoolConnectError)
+    }
+
+    async fn recycle(&self, client: &mut Client) -> RecycleResult<Self::Error> {
+        if client.is_closed() {
+            return Err(RecycleError::Message("connection is closed".into()));
+        }
+        client
+            .simple_query("SELECT 1")
+            .await
+            .map_err(|e| RecycleError::Message(e.to_string()))?;
+        Ok(())
+    }
+}
+
+type PgPool = Pool<PgManager>;
+
 pub struct PostgresConnection {
-    client: Client,
-    insert_stmt: Statement,
+    pool: PgPool,
     table: String,
+    max_insert_retries: u32,
+    schema_map: SchemaMap,
+    notify_channel: Option<String>,
 }
 
 #[derive(Debug)]
@@ -78,9 +412,16 @@ pub struct Postgres {
     password: String,
     db_name: String,
     table: String,
+    sslmode: SslMode,
+    ssl_root_cert: Option<String>,
+    pool_size: usize,
+    max_insert_retries: u32,
+    schema_map: SchemaMap,
+    notify_channel: Option<String>,
 }
 
 impl Postgres {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         host: String,
         port: u16,
@@ -88,6 +429,12 @@ impl Postgres {
         password: String,
         db_name: String,
         table: String,
+        sslmode: SslMode,
+        ssl_root_cert: Option<String>,
+        pool_size: usize,
+        max_insert_retries: u32,
+        schema_map: SchemaMap,
+        notify_channel: Option<String>,
     ) -> Postgres {
         Postgres {
             host,
@@ -96,6 +443,12 @@ impl Postgres {
             password,
             db_name,
             table,
+            sslmode,
+            ssl_root_cert,
+            pool_size,
+            max_insert_retries,
+            schema_map,
+            notify_channel,
         }
     }
 }
@@ -108,11 +461,17 @@ impl Db<PostgresConnection> for Postgres {
             "host={} port={} user={} password={} dbname={}",
             self.host, self.port, self.user, self.password, self.db_name
         );
-        let (client, connection) = tokio_postgres::connect(&conn_str, NoTls).await?;
-        tokio::spawn(Box::pin(connection));
+
+        // Run the schema setup over a one-off connection before handing
+        // things over to the pool.
+        let client = connect_client(&conn_str, self.sslmode, self.ssl_root_cert.as_deref()).await?;
         info!("Successfully connected to database");
 
         info!("Creating table {} if it does not exist", self.table);
+        let mut projected_columns_ddl = String::new();
+        for (_, col) in self.schema_map.projections() {
+            projected_columns_ddl.push_str(&format!("{} {},\n", col.column, column_ddl(&col.sql_type)?));
+        }
         let create_table_sql = format!(
             r#"
                 CREATE TABLE IF NOT EXISTS {} (
@@ -123,39 +482,279 @@ impl Db<PostgresConnection> for Postgres {
                     psn bigint not null,
                     timestamp bigint not null,
                     payload jsonb,
+                    {}
                     PRIMARY KEY (source, psn)
                 )
             "#,
-            self.table
+            self.table, projected_columns_ddl
         );
         let create_table_statement = client.prepare(&*create_table_sql).await?;
         client.execute(&create_table_statement, &[]).await?;
         info!("Created table");
 
-        let sql = format!(
-            r#"INSERT INTO {} (source, semantics, name, seq, psn, timestamp, payload)
-                 SELECT * FROM unnest($1,$2,$3,$4,$5,$6,$7)
-                 ON CONFLICT DO NOTHING"#,
-            &self.table
+        info!("Creating table {}_dead_letter if it does not exist", self.table);
+        let create_dead_letter_sql = format!(
+            r#"
+                CREATE TABLE IF NOT EXISTS {}_dead_letter (
+                    id bigserial PRIMARY KEY,
+                    source text not null,
+                    psn bigint not null,
+                    lamport bigint not null,
+                    raw bytea not null,
+                    error text not null,
+                    failed_at timestamptz not null default now()
+                )
+            "#,
+            self.table
         );
-        let types = vec![
-            Type::TEXT_ARRAY,
-            Type::TEXT_ARRAY,
-            Type::TEXT_ARRAY,
-            Type::INT8_ARRAY,
-            Type::INT8_ARRAY,
-            Type::INT8_ARRAY,
-            Type::JSONB_ARRAY,
-        ];
-        let insert_stmt = client.prepare_typed(&sql, &types).await?;
+        let create_dead_letter_statement = client.prepare(&*create_dead_letter_sql).await?;
+        client.execute(&create_dead_letter_statement, &[]).await?;
+        info!("Created dead letter table");
 
-        let conn = PostgresConnection {
-            client,
-            insert_stmt,
+        let manager = PgManager {
+            conn_str,
+            sslmode: self.sslmode,
+            ssl_root_cert: self.ssl_root_cert.clone(),
+        };
+        let pool = Pool::new(manager, self.pool_size);
+
+        Ok(PostgresConnection {
+            pool,
             table: self.table.clone(),
+            max_insert_retries: self.max_insert_retries,
+            schema_map: self.schema_map.clone(),
+            notify_channel: self.notify_channel.clone(),
+        })
+    }
+}
+
+impl PostgresConnection {
+    /// Writes the main batch, retrying retryable failures with exponential
+    /// backoff. If the batch keeps failing after `max_insert_retries`
+    /// attempts, or fails for a permanent reason (e.g. a constraint
+    /// violation), falls back to inserting row by row so only the offending
+    /// rows end up in the dead letter table instead of the whole batch.
+    /// Returns the `(source, psn)` pairs that actually ended up committed to
+    /// the main table, which may be a strict subset of `rows` if some rows
+    /// were dead-lettered by the row-by-row fallback.
+    async fn insert_batch_with_retry<'a>(
+        &self,
+        rows: &DbEventVec<'a>,
+    ) -> Result<Vec<(&'a str, i64)>> {
+        let mut backoff = ExponentialBackoff {
+            initial_interval: Duration::from_millis(100),
+            multiplier: 2.0,
+            max_interval: Duration::from_secs(30),
+            max_elapsed_time: None,
+            ..ExponentialBackoff::default()
+        };
+
+        let mut attempt = 0u32;
+        loop {
+            attempt += 1;
+            match self.try_insert_batch(rows).await {
+                Ok(()) => {
+                    return Ok(rows
+                        .sources
+                        .iter()
+                        .copied()
+                        .zip(rows.offsets.iter().copied())
+                        .collect())
+                }
+                Err(err) => {
+                    let retryable = err
+                        .downcast_ref::<tokio_postgres::Error>()
+                        .map(is_retryable)
+                        .unwrap_or(true);
+
+                    if !retryable || attempt >= self.max_insert_retries {
+                        warn!(
+                            "Batch insert permanently failed after {} attempt(s): {:?}. Falling back to row-by-row insert",
+                            attempt, err
+                        );
+                        return self.insert_rows_individually(rows).await;
+                    }
+
+                    let delay = backoff.next_backoff().unwrap_or(Duration::from_secs(30));
+                    warn!(
+                        "Batch insert attempt {} failed ({:?}), retrying in {:?}",
+                        attempt, err, delay
+                    );
+                    metrics::INSERT_RETRIES_TOTAL.inc();
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    async fn try_insert_batch(&self, rows: &DbEventVec<'_>) -> Result<()> {
+        let client = self.pool.get().await?;
+        let insert_stmt = prepare_insert_stmt(&client, &self.table, &rows.projected).await?;
+        let mut params: Vec<&(dyn ToSql + Sync)> = vec![
+            &rows.sources,
+            &rows.semantics,
+            &rows.names,
+            &rows.lamports,
+            &rows.offsets,
+            &rows.timestamps,
+            &rows.payloads,
+        ];
+        for (_, col) in &rows.projected {
+            params.push(col.as_sql_param());
+        }
+        client
+            .execute(
+                &insert_stmt,
+                // Make sure that the order of the fields here matches the INSERT statement in prepare_insert_stmt above
+                &params,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Inserts one row at a time, retrying each row's transient failures
+    /// with backoff just like [`PostgresConnection::insert_batch_with_retry`],
+    /// and routing only rows that are permanently rejected (e.g. a
+    /// constraint violation, or a transient error that outlived its
+    /// retries) into the dead letter table instead of losing them or
+    /// blocking the whole batch. Returns the `(source, psn)` pairs that were
+    /// actually committed to the main table.
+    async fn insert_rows_individually<'a>(&self, rows: &DbEventVec<'a>) -> Result<Vec<(&'a str, i64)>> {
+        let client = self.pool.get().await?;
+        let insert_stmt = prepare_insert_stmt(&client, &self.table, &rows.projected).await?;
+
+        let mut committed = Vec::with_capacity(rows.sources.len());
+
+        for i in 0..rows.sources.len() {
+            let source = vec![rows.sources[i]];
+            let semantics = vec![rows.semantics[i]];
+            let name = vec![rows.names[i]];
+            let lamport = vec![rows.lamports[i]];
+            let offset = vec![rows.offsets[i]];
+            let timestamp = vec![rows.timestamps[i]];
+            let payload = vec![rows.payloads[i].clone()];
+            let single_row_projected: Vec<(String, ProjectedColumn)> = rows
+                .projected
+                .iter()
+                .map(|(col_name, col)| (col_name.clone(), col.single_row(i)))
+                .collect();
+
+            let mut params: Vec<&(dyn ToSql + Sync)> = vec![
+                &source, &semantics, &name, &lamport, &offset, &timestamp, &payload,
+            ];
+            for (_, col) in &single_row_projected {
+                params.push(col.as_sql_param());
+            }
+
+            let mut backoff = ExponentialBackoff {
+                initial_interval: Duration::from_millis(100),
+                multiplier: 2.0,
+                max_interval: Duration::from_secs(30),
+                max_elapsed_time: None,
+                ..ExponentialBackoff::default()
+            };
+            let mut attempt = 0u32;
+            let outcome = loop {
+                attempt += 1;
+                match client.execute(&insert_stmt, &params).await {
+                    Ok(_) => break Ok(()),
+                    Err(err) => {
+                        if !is_retryable(&err) || attempt >= self.max_insert_retries {
+                            break Err(err);
+                        }
+
+                        let delay = backoff.next_backoff().unwrap_or(Duration::from_secs(30));
+                        warn!(
+                            "Row from source {} at psn {} insert attempt {} failed ({:?}), retrying in {:?}",
+                            rows.sources[i], rows.offsets[i], attempt, err, delay
+                        );
+                        metrics::INSERT_RETRIES_TOTAL.inc();
+                        tokio::time::sleep(delay).await;
+                    }
+                }
+            };
+
+            match outcome {
+                Ok(()) => committed.push((rows.sources[i], rows.offsets[i])),
+                Err(err) => {
+                    error!(
+                        "Row from source {} at psn {} permanently rejected: {:?}",
+                        rows.sources[i], rows.offsets[i], err
+                    );
+                    let dead_letter = [DeadLetterRow {
+                        source: rows.sources[i],
+                        psn: rows.offsets[i],
+                        lamport: rows.lamports[i],
+                        raw: serde_json::to_vec(&rows.payloads[i]).unwrap_or_default(),
+                        error: err.to_string(),
+                    }];
+                    // A failure to write the dead letter row is itself just a
+                    // transient/logging concern - it must not crash the
+                    // pipeline, which is exactly the failure mode dead
+                    // lettering exists to avoid.
+                    if let Err(dead_letter_err) = self.write_dead_letters(&dead_letter).await {
+                        error!(
+                            "Failed to write dead letter row for source {} at psn {}: {:?}",
+                            rows.sources[i], rows.offsets[i], dead_letter_err
+                        );
+                    }
+                }
+            }
+        }
+
+        Ok(committed)
+    }
+
+    /// Issues a `pg_notify(channel, payload)` per source touched by
+    /// `committed`, so a `LISTEN`ing downstream service can react without
+    /// polling the table. `committed` must only contain rows that actually
+    /// landed in the main table - NOTIFY payloads are capped at 8000 bytes
+    /// by Postgres, so we send one compact summary per source rather than
+    /// the events themselves.
+    async fn notify_batch(&self, committed: &[(&str, i64)]) -> Result<()> {
+        let channel = match &self.notify_channel {
+            Some(channel) => channel,
+            None => return Ok(()),
         };
 
-        Ok(conn)
+        let mut per_source: BTreeMap<&str, (i64, u64)> = BTreeMap::new();
+        for (source, psn) in committed {
+            let entry = per_source.entry(source).or_insert((*psn, 0));
+            entry.0 = entry.0.max(*psn);
+            entry.1 += 1;
+        }
+
+        let client = self.pool.get().await?;
+        let notify_stmt = client.prepare("SELECT pg_notify($1, $2)").await?;
+        for (source, (max_psn, count)) in per_source {
+            let payload = serde_json::json!({
+                "table": self.table,
+                "source": source,
+                "max_psn": max_psn,
+                "count": count,
+            })
+            .to_string();
+            client
+                .execute(&notify_stmt, &[&channel, &payload])
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn write_dead_letters(&self, dead_letters: &[DeadLetterRow<'_>]) -> Result<()> {
+        let client = self.pool.get().await?;
+        let dead_letter_stmt = prepare_dead_letter_stmt(&client, &self.table).await?;
+        for dl in dead_letters {
+            client
+                .execute(
+                    &dead_letter_stmt,
+                    &[&dl.source, &dl.psn, &dl.lamport, &dl.raw, &dl.error],
+                )
+                .await?;
+        }
+        metrics::DEAD_LETTERED_TOTAL.inc_by(dead_letters.len() as u64);
+        Ok(())
     }
 }
 
@@ -167,7 +766,8 @@ impl DbConnection for PostgresConnection {
         let num_rows = items.len();
         debug!("Preparing {} events", num_rows);
         let rows_suffix = if num_rows > 1 { "s" } else { "" };
-        let rows = DbEventVec::from(&*items);
+        metrics::BATCH_SIZE.observe(num_rows as f64);
+        let rows = DbEventVec::build(&items, &self.schema_map)?;
 
         let mut sources = rows.sources.clone();
         sources.sort_unstable();
@@ -179,21 +779,23 @@ impl DbConnection for PostgresConnection {
             "About to write {} record{} into DB. Source{}: {}",
             num_rows, rows_suffix, sources_suffix, sources,
         );
-        self.client
-            .execute(
-                &self.insert_stmt,
-                // Make sure that the order of the fields here matches the INSERT statement in the connect() method above
-                &[
-                    &rows.sources,
-                    &rows.semantics,
-                    &rows.names,
-                    &rows.lamports,
-                    &rows.offsets,
-                    &rows.timestamps,
-                    &rows.payloads,
-                ],
-            )
-            .await?;
+
+        if !rows.dead_letters.is_empty() {
+            // A failure to write these dead letter rows must not crash the
+            // pipeline either - that's exactly the failure mode dead
+            // lettering exists to avoid.
+            if let Err(err) = self.write_dead_letters(&rows.dead_letters).await {
+                error!("Failed to write dead letter rows: {:?}", err);
+            }
+        }
+
+        if !rows.sources.is_empty() {
+            let committed = self.insert_batch_with_retry(&rows).await?;
+            self.notify_batch(&committed).await?;
+            metrics::EVENTS_INSERTED_TOTAL.inc_by(committed.len() as u64);
+        }
+
+        metrics::INSERT_LATENCY_SECONDS.observe(start.elapsed().as_secs_f64());
 
         let elapsed = start.elapsed().as_millis();
         info!(
@@ -211,12 +813,13 @@ impl DbConnection for PostgresConnection {
 
     async fn get_offsets(&self) -> Result<OffsetMap> {
         info!("Querying initial offset map");
+        let client = self.pool.get().await?;
         let sql = format!(
             "SELECT source, MAX(psn) FROM {} GROUP BY source",
             self.table
         );
-        let query = self.client.prepare(&*sql).await?;
-        let rows = self.client.query(&query, &[]).await?;
+        let query = client.prepare(&*sql).await?;
+        let rows = client.query(&query, &[]).await?;
 
         let offsets: BTreeMap<_, _> = rows
             .into_iter()
@@ -278,9 +881,11 @@ mod tests {
                 serde_json::from_str(r#"{"foo":"foo"}"#).unwrap(),
                 serde_json::from_str(r#"{"bar":"bar"}"#).unwrap(),
             ],
+            dead_letters: vec![],
+            projected: vec![],
         };
 
-        let actual: DbEventVec = (&events).into();
+        let actual = DbEventVec::build(&events, &SchemaMap::default()).unwrap();
 
         assert_eq!(actual, expected);
     }
@@ -0,0 +1,70 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// Declares that the JSON value found at a pointer inside an event's payload
+/// should additionally be projected out into its own typed SQL column.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ColumnProjection {
+    pub column: String,
+    #[serde(rename = "type")]
+    pub sql_type: String,
+}
+
+/// Maps JSON pointers inside the payload (e.g. `$.price`) to the columns
+/// they should be projected into, loaded once at startup from
+/// `--schema-map`. The full payload is still stored in `payload` jsonb
+/// regardless of what is projected out of it.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SchemaMap(BTreeMap<String, ColumnProjection>);
+
+impl SchemaMap {
+    pub fn load(path: &Path) -> Result<SchemaMap> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read --schema-map file {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse --schema-map file {}", path.display()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn projections(&self) -> impl Iterator<Item = (&str, &ColumnProjection)> {
+        self.0.iter().map(|(pointer, col)| (pointer.as_str(), col))
+    }
+
+    /// Resolves a `$.a.b`-style pointer against a JSON payload.
+    pub fn extract<'a>(pointer: &str, payload: &'a Value) -> Option<&'a Value> {
+        let pointer = pointer.strip_prefix("$.").unwrap_or(pointer);
+        pointer.split('.').try_fold(payload, |v, key| v.get(key))
+    }
+}
+
+/// The Postgres column types a schema-map entry may declare.
+pub fn column_ddl(sql_type: &str) -> Result<&'static str> {
+    match sql_type {
+        "float8" | "double precision" => Ok("float8"),
+        "int8" | "bigint" => Ok("int8"),
+        "text" => Ok("text"),
+        "bool" | "boolean" => Ok("bool"),
+        "timestamptz" => Ok("timestamptz"),
+        other => Err(anyhow!("unsupported schema-map column type '{}'", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_resolves_dotted_pointer() {
+        let payload: Value = serde_json::from_str(r#"{"ts": {"iso": "2021-01-01T00:00:00Z"}}"#).unwrap();
+        assert_eq!(
+            SchemaMap::extract("$.ts.iso", &payload),
+            Some(&Value::String("2021-01-01T00:00:00Z".to_owned()))
+        );
+        assert_eq!(SchemaMap::extract("$.missing", &payload), None);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::db::{Db, DbConnection};
+use actyxos_sdk::event::{Event, Offset, OffsetMap, Payload, SourceId};
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::any::{AnyKind, AnyPoolOptions};
+use sqlx::{AnyPool, Row};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use tracing::info;
+
+/// A `sqlx`-backed sink supporting Postgres, MySQL, and SQLite behind a
+/// single `--db-url`, for users who don't need the Postgres-specific
+/// `unnest` batching, dead lettering, or schema projection that `Postgres`
+/// provides. The backend dialect is picked off the URL scheme at connect
+/// time via `sqlx`'s `Any` driver.
+pub struct SqlxDb {
+    db_url: String,
+    table: String,
+}
+
+impl SqlxDb {
+    pub fn new(db_url: String, table: String) -> SqlxDb {
+        SqlxDb { db_url, table }
+    }
+}
+
+pub struct SqlxConnection {
+    pool: AnyPool,
+    table: String,
+    kind: AnyKind,
+}
+
+#[async_trait]
+impl Db<SqlxConnection> for SqlxDb {
+    async fn connect(&self) -> Result<SqlxConnection> {
+        info!("Connecting to database at {}", self.db_url);
+        let pool = AnyPoolOptions::new().connect(&self.db_url).await?;
+        let kind = pool.any_kind();
+
+        info!("Creating table {} if it does not exist", self.table);
+        // `source` is bounded (rather than `text`) because it's part of the
+        // primary key: MySQL rejects a key over a TEXT/BLOB column without
+        // an explicit length (error 1170).
+        let create_table_sql = format!(
+            r#"
+                CREATE TABLE IF NOT EXISTS {} (
+                    source varchar(255) not null,
+                    semantics text not null,
+                    name text not null,
+                    seq bigint not null,
+                    psn bigint not null,
+                    timestamp bigint not null,
+                    payload text not null,
+                    PRIMARY KEY (source, psn)
+                )
+            "#,
+            self.table
+        );
+        sqlx::query(&create_table_sql).execute(&pool).await?;
+        info!("Created table");
+
+        Ok(SqlxConnection {
+            pool,
+            table: self.table.clone(),
+            kind,
+        })
+    }
+}
+
+/// Picks the bulk-insert dialect for `kind`: Postgres and SQLite both
+/// understand `INSERT ... ON CONFLICT DO NOTHING`; MySQL instead needs
+/// `INSERT IGNORE`.
+fn insert_clauses(kind: AnyKind) -> (&'static str, &'static str) {
+    match kind {
+        AnyKind::MySql => ("INSERT IGNORE INTO", ""),
+        _ => ("INSERT INTO", " ON CONFLICT DO NOTHING"),
+    }
+}
+
+#[async_trait]
+impl DbConnection for SqlxConnection {
+    async fn insert(&self, items: Vec<Event<Payload>>) -> Result<()> {
+        if items.is_empty() {
+            return Ok(());
+        }
+
+        let (insert_verb, conflict_clause) = insert_clauses(self.kind);
+
+        // Postgres only accepts numbered `$n` placeholders; MySQL and SQLite
+        // take `?`.
+        let placeholders = (0..items.len())
+            .map(|row| {
+                let params = (1..=7)
+                    .map(|col| match self.kind {
+                        AnyKind::Postgres => format!("${}", row * 7 + col),
+                        _ => "?".to_owned(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("({})", params)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sql = format!(
+            "{} {} (source, semantics, name, seq, psn, timestamp, payload) VALUES {}{}",
+            insert_verb, self.table, placeholders, conflict_clause
+        );
+
+        let mut query = sqlx::query(&sql);
+        for e in &items {
+            query = query
+                .bind(e.stream.source.as_str().to_owned())
+                .bind(e.stream.semantics.as_str().to_owned())
+                .bind(e.stream.name.as_str().to_owned())
+                .bind(e.lamport.as_i64())
+                .bind((e.offset - Offset::ZERO) as i64)
+                .bind(e.timestamp.as_i64())
+                .bind(serde_json::to_string(&e.payload)?);
+        }
+        query.execute(&self.pool).await?;
+
+        Ok(())
+    }
+
+    async fn get_offsets(&self) -> Result<OffsetMap> {
+        info!("Querying offset map");
+        let sql = format!(
+            "SELECT source, MAX(psn) as psn FROM {} GROUP BY source",
+            self.table
+        );
+        let rows = sqlx::query(&sql).fetch_all(&self.pool).await?;
+
+        let offsets: BTreeMap<_, _> = rows
+            .into_iter()
+            .map(|row| {
+                let source: String = row.get("source");
+                let psn: i64 = row.get("psn");
+                (
+                    SourceId::new(source).unwrap(),
+                    Offset::try_from(psn as u64).unwrap(),
+                )
+            })
+            .collect();
+
+        Ok(OffsetMap::from(offsets))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mysql_uses_insert_ignore_others_use_on_conflict() {
+        assert_eq!(insert_clauses(AnyKind::MySql), ("INSERT IGNORE INTO", ""));
+        assert_eq!(
+            insert_clauses(AnyKind::Postgres),
+            ("INSERT INTO", " ON CONFLICT DO NOTHING")
+        );
+        assert_eq!(
+            insert_clauses(AnyKind::Sqlite),
+            ("INSERT INTO", " ON CONFLICT DO NOTHING")
+        );
+    }
+}